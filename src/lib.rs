@@ -1,61 +1,423 @@
+use std::cell::Cell;
 use std::ffi::CStr;
 use std::fmt::{self, Write};
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, IoSliceMut};
 use std::ops::Range;
 use std::os::unix::fs::FileExt;
 use std::str::{self, FromStr};
+use std::sync::Arc;
 
 use corosensei::{Coroutine, CoroutineResult};
-use libc::{ino_t, pid_t};
-use log::{error, info};
+use libc::{ENOSYS, EPERM, ino_t, pid_t};
+use log::{error, info, warn};
 use memchr::memmem;
+use nix::sys::uio::{RemoteIoVec, process_vm_readv};
+use nix::unistd::Pid;
+use regex::bytes::Regex;
+
+/// The default size of the sliding window used to stream each region, chosen to
+/// keep peak memory low without making too many read syscalls.
+pub const DEFAULT_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// How far past the end of a block to read when recovering a match's printed
+/// string that runs up against the block boundary.
+const TEXT_LOOKAHEAD: usize = 4096;
+
+/// How many small mappings to gather into a single batched `process_vm_readv`
+/// call, to cut down on syscalls for processes with many small mappings.
+const MAX_BATCH_REGIONS: usize = 16;
+
+/// How many trailing bytes of a block to carry over into the next one when
+/// searching with a regex needle, since unlike a literal needle its match
+/// length isn't known up front. Matches longer than this that straddle a
+/// block boundary may be missed.
+const REGEX_CARRY_LEN: usize = 4096;
+
+/// A [`Finder::with_region_filter`] predicate.
+type RegionFilter = Arc<dyn Fn(&Map) -> bool>;
+
+/// Reads one or more (possibly non-contiguous) ranges of `pid`'s address space
+/// in a single `process_vm_readv(2)` call, gathering them via its `remote_iov`
+/// array.
+fn vm_readv(pid: pid_t, regions: &mut [(u64, &mut [u8])]) -> io::Result<()> {
+    let remote: Vec<RemoteIoVec> = regions
+        .iter()
+        .map(|(addr, buf)| RemoteIoVec {
+            base: *addr as usize,
+            len: buf.len(),
+        })
+        .collect();
+    let mut local: Vec<IoSliceMut<'_>> = regions
+        .iter_mut()
+        .map(|(_, buf)| IoSliceMut::new(buf))
+        .collect();
+    let want: usize = remote.iter().map(|r| r.len).sum();
+
+    let got = process_vm_readv(Pid::from_raw(pid), &mut local, &remote)
+        .map_err(|errno| io::Error::from_raw_os_error(errno as i32))?;
+
+    if got != want {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "short process_vm_readv read",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Applies a single in-place patch for a match of length `match_len` at `pos`
+/// within `map`, returning whether the write succeeded.
+fn apply_replacement(
+    mem: &File,
+    mem_path: &str,
+    pos: usize,
+    map: &Map,
+    replacement: &[u8],
+    pad: bool,
+    match_len: usize,
+) -> bool {
+    if !map.perms.write {
+        warn!("{:?} is read-only, skipping replacement at {pos:#x}", map.address);
+        return false;
+    }
+
+    if replacement.len() != match_len && !pad {
+        warn!(
+            "replacement length does not match match length at {pos:#x}, skipping (pass --pad to allow)"
+        );
+        return false;
+    }
+
+    // Shorter replacements are zero-padded to the match's length; longer ones
+    // overwrite the bytes past the match, as long as that doesn't grow past
+    // the end of the mapping the match was found in (bytes can't be shifted
+    // into whatever mapping, or gap, follows it).
+    let mut padded;
+    let buf: &[u8] = if replacement.len() < match_len {
+        padded = replacement.to_vec();
+        padded.resize(match_len, 0);
+        &padded
+    } else {
+        replacement
+    };
+
+    if pos + buf.len() > map.address.end {
+        warn!(
+            "replacement at {pos:#x} would grow past the end of {:?}, skipping",
+            map.address
+        );
+        return false;
+    }
+
+    apply_write(mem, mem_path, pos, buf)
+}
+
+/// Writes `buf` to `mem` at `pos`, logging and reporting failure on any error
+/// or short write (`write_at`/`pwrite(2)` can write fewer bytes than asked).
+fn apply_write(mem: &File, mem_path: &str, pos: usize, buf: &[u8]) -> bool {
+    match mem.write_at(buf, pos as u64) {
+        Ok(n) if n == buf.len() => true,
+        Ok(n) => {
+            error!("short write to {mem_path} at {pos:#x}: wrote {n} of {} bytes", buf.len());
+            false
+        }
+        Err(err) => {
+            error!("could not write to {mem_path} at {pos:#x}: {err}");
+            false
+        }
+    }
+}
 
 pub struct Finder<'n> {
-    finder: memmem::Finder<'n>,
+    needle: Needle<'n>,
+    replace: Option<Replace<'n>>,
+    block_size: usize,
+    filter: Option<RegionFilter>,
 }
 
 impl<'n> Finder<'n> {
+    /// Searches for a literal needle, matched byte-for-byte via `memchr::memmem`.
+    ///
+    /// `needle` can be any byte sequence, so this also covers a `--hex`-parsed
+    /// one: the needle doesn't have to be valid UTF-8.
     #[inline]
     pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> Finder<'n> {
-        let finder = memmem::Finder::new(needle);
-        Self { finder }
+        Self {
+            needle: Needle::Literal(Box::new(memmem::Finder::new(needle))),
+            replace: None,
+            block_size: DEFAULT_BLOCK_SIZE,
+            filter: None,
+        }
+    }
+
+    /// Searches for every non-overlapping match of `regex` instead of a
+    /// literal needle.
+    #[inline]
+    pub fn new_regex(regex: Regex) -> Finder<'n> {
+        Self {
+            needle: Needle::Regex(regex),
+            replace: None,
+            block_size: DEFAULT_BLOCK_SIZE,
+            filter: None,
+        }
+    }
+
+    /// Patches every match in place with `replacement` instead of just reporting it.
+    ///
+    /// Only regions whose permissions allow writing are patched. Unless `pad` is
+    /// set, `replacement` must be exactly as long as the needle, since mapped
+    /// bytes cannot be safely shifted around a match.
+    #[inline]
+    pub fn with_replace(mut self, replacement: &'n [u8], pad: bool) -> Self {
+        self.replace = Some(Replace {
+            bytes: replacement,
+            pad,
+        });
+        self
+    }
+
+    /// Sets the size of the sliding window used to stream each region, instead
+    /// of the [`DEFAULT_BLOCK_SIZE`].
+    #[inline]
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// Restricts which regions are scanned.
+    ///
+    /// `predicate` is consulted with the already-parsed [`Map`] entry for each
+    /// region before it is read; only regions for which it returns `true` are
+    /// scanned. This can be used to e.g. search only writable regions
+    /// (`|m| m.perms.write`), only anonymous ones (`|m| m.pathname.is_empty()`),
+    /// only `[heap]`/`[stack]` (`|m| m.pathname == "[heap]"`), or only an
+    /// address range (`|m| range.contains(&m.address.start)`).
+    #[inline]
+    pub fn with_region_filter<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Map) -> bool + 'static,
+    {
+        self.filter = Some(Arc::new(predicate));
+        self
     }
 
     #[inline]
     pub fn find_iter(&self, pid: pid_t) -> io::Result<FindIter> {
-        FindIter::new(self.finder.as_ref(), pid)
+        FindIter::new(
+            self.needle.detach(),
+            self.replace,
+            self.block_size,
+            self.filter.clone(),
+            pid,
+        )
     }
 }
 
+#[derive(Clone, Copy)]
+struct Replace<'n> {
+    bytes: &'n [u8],
+    pad: bool,
+}
+
+/// The pattern a [`Finder`] searches for: either a literal needle (matched via
+/// `memchr::memmem`) or a regular expression (matched via `regex::bytes`).
+enum Needle<'n> {
+    Literal(Box<memmem::Finder<'n>>),
+    Regex(Regex),
+}
+
+impl<'n> Needle<'n> {
+    // FIXME: It would be great to make the coroutine depend on `'n` once it is possible.
+    fn detach(&self) -> OwnedNeedle {
+        match self {
+            Needle::Literal(finder) => {
+                OwnedNeedle::Literal(Box::new((**finder).as_ref().into_owned()))
+            }
+            Needle::Regex(regex) => OwnedNeedle::Regex(regex.clone()),
+        }
+    }
+}
+
+/// An owned form of [`Needle`] that can be moved into the `'static` coroutine
+/// driving a [`FindIter`].
+enum OwnedNeedle {
+    Literal(Box<memmem::Finder<'static>>),
+    Regex(Regex),
+}
+
+impl OwnedNeedle {
+    /// Yields the `(offset, length)` of every non-overlapping match in `haystack`.
+    fn find_iter<'h>(&'h self, haystack: &'h [u8]) -> Box<dyn Iterator<Item = (usize, usize)> + 'h> {
+        match self {
+            OwnedNeedle::Literal(finder) => {
+                let len = finder.needle().len();
+                Box::new(finder.find_iter(haystack).map(move |pos| (pos, len)))
+            }
+            OwnedNeedle::Regex(regex) => {
+                Box::new(regex.find_iter(haystack).map(|m| (m.start(), m.len())))
+            }
+        }
+    }
+
+    /// How many trailing bytes of a block must be carried over into the next
+    /// one so a match straddling the boundary is still found: exact for a
+    /// literal needle, or [`REGEX_CARRY_LEN`] for a regex, whose match length
+    /// isn't known up front.
+    fn carry_len(&self) -> usize {
+        match self {
+            OwnedNeedle::Literal(finder) => finder.needle().len().saturating_sub(1),
+            OwnedNeedle::Regex(_) => REGEX_CARRY_LEN,
+        }
+    }
+}
+
+/// An event produced while scanning a process's memory.
+#[derive(Debug)]
+pub enum FindEvent {
+    /// A match of length `len` was found at `offset`, rendered as `text`.
+    Match {
+        offset: usize,
+        len: usize,
+        text: String,
+    },
+    /// Replacement was attempted for the matches in a region, with `succeeded`
+    /// counting the patches written and `failed` those that were skipped
+    /// (read-only region, length mismatch without `pad`, past the region's
+    /// end) or whose write failed.
+    Replaced {
+        region: Range<usize>,
+        succeeded: usize,
+        failed: usize,
+    },
+}
+
 #[must_use = "iterators are lazy and do nothing unless consumed"]
 pub struct FindIter {
-    coroutine: Coroutine<(), io::Result<(usize, String)>, ()>,
+    coroutine: Coroutine<(), io::Result<FindEvent>, ()>,
 }
 
 impl FindIter {
-    fn new(finder: memmem::Finder<'_>, pid: pid_t) -> io::Result<Self> {
+    fn new(
+        needle: OwnedNeedle,
+        replace: Option<Replace<'_>>,
+        block_size: usize,
+        filter: Option<RegionFilter>,
+        pid: pid_t,
+    ) -> io::Result<Self> {
         let maps = File::open(format!("/proc/{pid}/maps"))?;
         let maps = BufReader::new(maps);
 
         let mem_path = format!("/proc/{pid}/mem");
-        let mem = File::open(&mem_path)?;
+        let mem = OpenOptions::new()
+            .read(true)
+            .write(replace.is_some())
+            .open(&mem_path)?;
 
-        // FIXME: It would be great to make the coroutine depend on `'n` once it is possible.
-        let finder = finder.into_owned();
+        let replace = replace.map(|r| (r.bytes.to_vec(), r.pad));
         let coroutine = Coroutine::new(move |yielder, _input| {
-            for map in maps.lines() {
-                let map = match map {
-                    Ok(map) => map,
+            let use_vm_readv = Cell::new(true);
+
+            // Reads one or more (possibly non-contiguous) regions in a single
+            // `process_vm_readv(2)` call when available, permanently falling
+            // back to `/proc/{pid}/mem` once it reports `EPERM`/`ENOSYS`.
+            let read_regions = |regions: &mut [(u64, &mut [u8])]| -> io::Result<()> {
+                if use_vm_readv.get() {
+                    match vm_readv(pid, regions) {
+                        Ok(()) => return Ok(()),
+                        Err(err)
+                            if matches!(err.raw_os_error(), Some(EPERM) | Some(ENOSYS)) =>
+                        {
+                            warn!(
+                                "process_vm_readv unavailable for pid {pid} ({err}), falling back to {mem_path}"
+                            );
+                            use_vm_readv.set(false);
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+
+                for (addr, buf) in regions.iter_mut() {
+                    mem.read_exact_at(buf, *addr)?;
+                }
+                Ok(())
+            };
+
+            // Renders the printed form of a match whose bytes start at `bytes`,
+            // reading a little further past what we already have (up to
+            // `region_end`) if a C string or UTF-8 sequence runs off the end of it.
+            let render_text = |bytes: &[u8], read_so_far_end: usize, region_end: usize| -> String {
+                if let Ok(s) = CStr::from_bytes_until_nul(bytes) {
+                    return s.to_string_lossy().into_owned();
+                }
+
+                let mut valid_len = 0;
+                for len in 1..=bytes.len() {
+                    if str::from_utf8(&bytes[..len]).is_ok() {
+                        valid_len = len;
+                    } else {
+                        return str::from_utf8(&bytes[..valid_len]).unwrap().to_owned();
+                    }
+                }
+
+                if read_so_far_end >= region_end {
+                    return str::from_utf8(bytes).unwrap_or_default().to_owned();
+                }
+
+                let extra_len = TEXT_LOOKAHEAD.min(region_end - read_so_far_end);
+                let mut extra = vec![0; extra_len];
+                if read_regions(&mut [(read_so_far_end as u64, &mut extra)]).is_err() {
+                    return str::from_utf8(bytes).unwrap_or_default().to_owned();
+                }
+
+                let mut combined = bytes.to_vec();
+                combined.extend_from_slice(&extra);
+
+                if let Ok(s) = CStr::from_bytes_until_nul(&combined) {
+                    return s.to_string_lossy().into_owned();
+                }
+
+                let mut valid_len = 0;
+                for len in 1..=combined.len() {
+                    if str::from_utf8(&combined[..len]).is_ok() {
+                        valid_len = len;
+                    } else {
+                        break;
+                    }
+                }
+                str::from_utf8(&combined[..valid_len]).unwrap().to_owned()
+            };
+
+            // Whether a parsed map should be scanned at all: readable, not the
+            // vDSO variable data, and passing the caller's region filter.
+            let accept = |line: &str| -> Option<Map> {
+                let map = line.parse::<Map>().ok()?;
+                if !map.perms.read || map.pathname.starts_with("[vvar") {
+                    return None;
+                }
+                if let Some(filter) = &filter {
+                    if !filter(&map) {
+                        return None;
+                    }
+                }
+                Some(map)
+            };
+
+            let mut lines = maps.lines().peekable();
+
+            while let Some(line) = lines.next() {
+                let line = match line {
+                    Ok(line) => line,
                     Err(err) => {
                         yielder.suspend(Err(err));
                         continue;
                     }
                 };
-                let map = match map.parse::<Map>() {
+                let map = match line.parse::<Map>() {
                     Ok(map) => map,
                     Err(err) => {
-                        error!("could not parse map: {map} ({err:?})");
+                        error!("could not parse map: {line} ({err:?})");
                         yielder.suspend(Err(io::Error::from(io::ErrorKind::InvalidData)));
                         continue;
                     }
@@ -63,44 +425,197 @@ impl FindIter {
 
                 info!("{map}");
 
-                if !map.perms.read {
+                if !map.perms.read || map.pathname.starts_with("[vvar") {
                     continue;
                 }
 
-                if map.pathname.starts_with("[vvar") {
-                    continue;
+                if let Some(filter) = &filter {
+                    if !filter(&map) {
+                        continue;
+                    }
                 }
 
-                let mut haystack = vec![0; map.address.end - map.address.start];
+                if map.address.end - map.address.start <= block_size {
+                    // Gather a run of small, already-qualifying mappings and read
+                    // them all in a single batched `process_vm_readv` call.
+                    let mut batch = vec![map];
+
+                    while batch.len() < MAX_BATCH_REGIONS {
+                        let Some(Ok(next_line)) = lines.peek() else {
+                            break;
+                        };
+                        let Some(next_map) = accept(next_line) else {
+                            break;
+                        };
+                        if next_map.address.end - next_map.address.start > block_size {
+                            break;
+                        }
+
+                        info!("{next_map}");
+                        batch.push(next_map);
+                        lines.next();
+                    }
+
+                    let mut bufs: Vec<Vec<u8>> = batch
+                        .iter()
+                        .map(|m| vec![0; m.address.end - m.address.start])
+                        .collect();
+
+                    // Whether each region's buffer ended up holding valid
+                    // data, so a single bad region in the batch (e.g. one
+                    // that got unmapped since the `/proc/{pid}/maps`
+                    // snapshot was taken) doesn't cost the others their matches.
+                    let mut read_ok = vec![true; batch.len()];
+
+                    {
+                        let mut regions: Vec<(u64, &mut [u8])> = batch
+                            .iter()
+                            .zip(bufs.iter_mut())
+                            .map(|(m, buf)| (m.address.start as u64, buf.as_mut_slice()))
+                            .collect();
+
+                        if let Err(err) = read_regions(&mut regions) {
+                            warn!(
+                                "could not batch-read {mem_path} for {} regions starting at {:#x} ({err}), retrying individually",
+                                batch.len(),
+                                batch[0].address.start
+                            );
+
+                            for (ok, (map, buf)) in
+                                read_ok.iter_mut().zip(batch.iter().zip(bufs.iter_mut()))
+                            {
+                                if let Err(err) = read_regions(&mut [(
+                                    map.address.start as u64,
+                                    buf.as_mut_slice(),
+                                )]) {
+                                    error!(
+                                        "could not read {mem_path} at {:#x}: {err}",
+                                        map.address.start
+                                    );
+                                    yielder.suspend(Err(err));
+                                    *ok = false;
+                                }
+                            }
+                        }
+                    }
+
+                    for ((map, window), region_read_ok) in
+                        batch.into_iter().zip(bufs).zip(read_ok)
+                    {
+                        if !region_read_ok {
+                            continue;
+                        }
+
+                        let mut region_succeeded = 0;
+                        let mut region_failed = 0;
+
+                        for (spos, mlen) in needle.find_iter(&window) {
+                            let pos = map.address.start + spos;
+                            let text = render_text(&window[spos..], map.address.end, map.address.end);
+
+                            yielder.suspend(Ok(FindEvent::Match {
+                                offset: pos,
+                                len: mlen,
+                                text,
+                            }));
+
+                            if let Some((replacement, pad)) = &replace {
+                                let ok = apply_replacement(
+                                    &mem, &mem_path, pos, &map, replacement, *pad, mlen,
+                                );
+                                if ok {
+                                    region_succeeded += 1;
+                                } else {
+                                    region_failed += 1;
+                                }
+                            }
+                        }
+
+                        if replace.is_some() && (region_succeeded > 0 || region_failed > 0) {
+                            yielder.suspend(Ok(FindEvent::Replaced {
+                                region: map.address.clone(),
+                                succeeded: region_succeeded,
+                                failed: region_failed,
+                            }));
+                        }
+                    }
 
-                if let Err(err) = mem.read_exact_at(&mut haystack, map.address.start as u64) {
-                    error!("could not read {mem_path} at {:?}: {err}", map.address);
-                    yielder.suspend(Err(err));
                     continue;
                 }
 
-                for spos in finder.find_iter(&haystack) {
-                    let pos = map.address.start + spos;
+                // Large region: stream it in fixed-size blocks rather than
+                // allocating it all at once, carrying over the trailing bytes
+                // of each block (see `OwnedNeedle::carry_len`) so a match
+                // straddling a block boundary is still found.
+                let carry_len_max = needle.carry_len();
+                let mut carry: Vec<u8> = Vec::new();
+                let mut cursor = map.address.start;
+                // The end of the previously scanned window: a match that
+                // already fits entirely before it was necessarily found (and
+                // reported) while scanning that window, since the carry is
+                // just its trailing bytes. Only `carry_len_max` bounds how far
+                // a literal needle's exact length can be; for a regex it's
+                // just a practical limit, so re-scanning the carried-over
+                // bytes can otherwise re-yield a match that doesn't straddle
+                // the boundary.
+                let mut prev_window_end = map.address.start;
+                let mut region_succeeded = 0;
+                let mut region_failed = 0;
+
+                while cursor < map.address.end {
+                    let read_len = block_size.min(map.address.end - cursor);
+                    let mut block = vec![0; read_len];
+
+                    if let Err(err) = read_regions(&mut [(cursor as u64, &mut block)]) {
+                        error!("could not read {mem_path} at {cursor:#x}..{:#x}: {err}", cursor + read_len);
+                        yielder.suspend(Err(err));
+                        break;
+                    }
 
-                    let bytes = &haystack[spos..];
+                    let window_start = cursor - carry.len();
+                    let mut window = carry;
+                    window.extend_from_slice(&block);
+                    let window_end = window_start + window.len();
 
-                    if let Ok(s) = CStr::from_bytes_until_nul(bytes) {
-                        let s = s.to_string_lossy().into_owned();
-                        yielder.suspend(Ok((pos, s)));
-                        continue;
-                    }
+                    for (spos, mlen) in needle.find_iter(&window) {
+                        let pos = window_start + spos;
+                        if pos + mlen <= prev_window_end {
+                            continue;
+                        }
 
-                    let mut s = "";
-                    for len in 1.. {
-                        if let Ok(ok) = str::from_utf8(&bytes[..len]) {
-                            s = ok;
-                        } else {
-                            break;
+                        let text = render_text(&window[spos..], window_end, map.address.end);
+
+                        yielder.suspend(Ok(FindEvent::Match {
+                            offset: pos,
+                            len: mlen,
+                            text,
+                        }));
+
+                        if let Some((replacement, pad)) = &replace {
+                            let ok = apply_replacement(
+                                &mem, &mem_path, pos, &map, replacement, *pad, mlen,
+                            );
+                            if ok {
+                                region_succeeded += 1;
+                            } else {
+                                region_failed += 1;
+                            }
                         }
                     }
 
-                    let s = s.to_owned();
-                    yielder.suspend(Ok((pos, s)));
+                    prev_window_end = window_end;
+                    let keep = carry_len_max.min(window.len());
+                    carry = window.split_off(window.len() - keep);
+
+                    cursor += read_len;
+                }
+
+                if replace.is_some() && (region_succeeded > 0 || region_failed > 0) {
+                    yielder.suspend(Ok(FindEvent::Replaced {
+                        region: map.address.clone(),
+                        succeeded: region_succeeded,
+                        failed: region_failed,
+                    }));
                 }
             }
         });
@@ -110,7 +625,7 @@ impl FindIter {
 }
 
 impl Iterator for FindIter {
-    type Item = io::Result<(usize, String)>;
+    type Item = io::Result<FindEvent>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.coroutine.done() {
@@ -124,17 +639,18 @@ impl Iterator for FindIter {
     }
 }
 
+/// The `rwxp`/`rwxs` permission bits of a [`Map`] entry.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
-struct Perms {
-    read: bool,
-    write: bool,
-    execute: bool,
-    shared: bool,
+pub struct Perms {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+    pub shared: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
-struct ParsePermsError;
+pub struct ParsePermsError;
 
 impl FromStr for Perms {
     type Err = ParsePermsError;
@@ -205,19 +721,21 @@ impl fmt::Display for Perms {
     }
 }
 
+/// A single entry of `/proc/{pid}/maps`, describing one mapped region of a
+/// process's address space.
 #[derive(PartialEq, Eq, Clone, Debug)]
-struct Map {
-    address: Range<usize>,
-    perms: Perms,
-    offset: usize,
-    dev: String,
-    inode: ino_t,
-    pathname: String,
+pub struct Map {
+    pub address: Range<usize>,
+    pub perms: Perms,
+    pub offset: usize,
+    pub dev: String,
+    pub inode: ino_t,
+    pub pathname: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
-struct ParseMapError;
+pub struct ParseMapError;
 
 impl FromStr for Map {
     type Err = ParseMapError;
@@ -313,4 +831,53 @@ mod tests {
         assert_eq!(map, s.parse().unwrap());
         assert_eq!(s, map.to_string());
     }
+
+    /// Exercises the large-region streaming path's block-carry logic: many
+    /// copies of a marker are placed at a spacing that shares no common
+    /// factor with `block_size`, so across enough copies some are guaranteed
+    /// to straddle a block boundary regardless of where the backing mapping
+    /// happens to start. Every copy must be found exactly once: a broken
+    /// carry either misses a straddling match or re-reports one that was
+    /// already found in the previous block.
+    #[test]
+    fn find_iter_finds_every_match_across_block_boundaries() {
+        const MARKER: &[u8] = b"MEM_FIND_TEST_MARKER_0xBEEF";
+        const SPACING: usize = 101;
+        const COUNT: usize = 200;
+        const BLOCK_SIZE: usize = 64;
+
+        let mut haystack = vec![0u8; COUNT * SPACING + MARKER.len() + BLOCK_SIZE];
+        let mut expected: Vec<usize> = (0..COUNT).map(|i| i * SPACING).collect();
+        for &at in &expected {
+            haystack[at..at + MARKER.len()].copy_from_slice(MARKER);
+        }
+
+        let buf_addr = haystack.as_ptr() as usize;
+        let buf_len = haystack.len();
+
+        let finder = Finder::new(MARKER)
+            .with_block_size(BLOCK_SIZE)
+            .with_region_filter(move |m| m.address.contains(&buf_addr));
+
+        let mut found: Vec<usize> = finder
+            .find_iter(std::process::id() as pid_t)
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter_map(|event| match event {
+                FindEvent::Match { offset, len, .. }
+                    if (buf_addr..buf_addr + buf_len).contains(&offset) =>
+                {
+                    assert_eq!(len, MARKER.len());
+                    Some(offset - buf_addr)
+                }
+                _ => None,
+            })
+            .collect();
+        found.sort_unstable();
+        expected.sort_unstable();
+
+        assert_eq!(found, expected);
+
+        drop(haystack);
+    }
 }
@@ -4,8 +4,9 @@ use std::{fs, io};
 
 use clap::Parser;
 use libc::pid_t;
-use mem_find::Finder;
+use mem_find::{DEFAULT_BLOCK_SIZE, FindEvent, Finder};
 use nix::unistd::{Uid, User};
+use regex::bytes::Regex;
 
 macro_rules! unwrap_or_continue {
     ($result:expr) => {
@@ -23,12 +24,50 @@ macro_rules! unwrap_or_continue {
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// The string to search for.
+    /// The string to search for. Interpreted as hex bytes or a regex instead
+    /// of a literal UTF-8 string if `--hex`/`--regex` is given.
     needle: String,
 
     /// The PID of the process to search.
     #[clap(id = "PID")]
     pids: Vec<pid_t>,
+
+    /// Interpret the needle as whitespace-separated hex bytes (e.g. "de ad be
+    /// ef") instead of a UTF-8 string, so non-UTF-8 byte sequences can be
+    /// searched for too.
+    #[clap(long, conflicts_with = "regex")]
+    hex: bool,
+
+    /// Interpret the needle as a regular expression instead of a literal
+    /// string, matched against each region's raw bytes.
+    #[clap(long)]
+    regex: bool,
+
+    /// Patch every match in place with these bytes instead of just reporting it.
+    #[clap(long)]
+    replace: Option<String>,
+
+    /// Interpret `--replace` as whitespace-separated hex bytes (e.g. "de ad
+    /// be ef") instead of a UTF-8 string, so a `--hex` match can be patched
+    /// with arbitrary non-UTF-8 bytes too.
+    #[clap(long, requires = "replace")]
+    replace_hex: bool,
+
+    /// Allow `--replace` bytes of a different length than the needle, since
+    /// mapped bytes cannot be shifted: shorter replacements are padded and
+    /// longer ones overwrite the bytes following the match.
+    #[clap(long, requires = "replace")]
+    pad: bool,
+
+    /// The size in bytes of the sliding window each region is streamed in.
+    #[clap(long, default_value_t = DEFAULT_BLOCK_SIZE)]
+    block_size: usize,
+
+    /// Only search processes owned by this user (name or UID). Applies to
+    /// explicitly given PIDs as well as to the scan of every process in
+    /// /proc that happens when no PIDs are given.
+    #[clap(long, alias = "uid")]
+    user: Option<String>,
 }
 
 fn main() -> ExitCode {
@@ -41,20 +80,106 @@ fn main() -> ExitCode {
 
 impl Args {
     fn run(&self) -> ExitCode {
-        let finder = Finder::new(&self.needle);
+        let hex_needle;
+        let hex_replace;
+        let mut finder = if self.regex {
+            let regex = match Regex::new(&self.needle) {
+                Ok(regex) => regex,
+                Err(err) => {
+                    eprintln!("invalid regex: {err}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            Finder::new_regex(regex)
+        } else if self.hex {
+            hex_needle = match Self::parse_hex(&self.needle) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    eprintln!("invalid hex needle: {err}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            Finder::new(&hex_needle)
+        } else {
+            Finder::new(&self.needle)
+        };
+        finder = finder.with_block_size(self.block_size);
+        if let Some(replace) = &self.replace {
+            let replacement = if self.replace_hex {
+                hex_replace = match Self::parse_hex(replace) {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        eprintln!("invalid hex replacement: {err}");
+                        return ExitCode::FAILURE;
+                    }
+                };
+                hex_replace.as_slice()
+            } else {
+                replace.as_bytes()
+            };
+            finder = finder.with_replace(replacement, self.pad);
+        }
+
+        let pids = if self.pids.is_empty() {
+            match Self::all_pids() {
+                Ok(pids) => pids,
+                Err(err) => {
+                    eprintln!("could not list /proc: {err}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        } else {
+            self.pids.clone()
+        };
+
+        let user = match &self.user {
+            Some(user) => match Self::resolve_uid(user) {
+                Ok(uid) => Some(uid),
+                Err(err) => {
+                    eprintln!("{err}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            None => None,
+        };
 
         let mut found = false;
-        for pid in self.pids.iter().copied() {
+        let mut replace_failed = false;
+        for pid in pids {
+            if let Some(user) = user {
+                match fs::metadata(format!("/proc/{pid}")) {
+                    Ok(metadata) if metadata.uid() == user.as_raw() => {}
+                    _ => continue,
+                }
+            }
+
             unwrap_or_continue!(self.print_info(pid));
 
             for res in unwrap_or_continue!(finder.find_iter(pid)) {
-                let (pos, s) = unwrap_or_continue!(res);
-
-                println!("{pos:08x}: {s:?}");
-                found = true;
+                match unwrap_or_continue!(res) {
+                    FindEvent::Match { offset, text, .. } => {
+                        println!("{offset:08x}: {text:?}");
+                        found = true;
+                    }
+                    FindEvent::Replaced {
+                        region,
+                        succeeded,
+                        failed,
+                    } => {
+                        println!(
+                            "{:08x}-{:08x}: replaced {succeeded}, failed {failed}",
+                            region.start, region.end
+                        );
+                        replace_failed |= failed > 0;
+                    }
+                }
             }
         }
 
+        if replace_failed {
+            return ExitCode::FAILURE;
+        }
+
         if found {
             ExitCode::SUCCESS
         } else {
@@ -63,6 +188,36 @@ impl Args {
         }
     }
 
+    /// Lists every PID currently in `/proc`, for when the user doesn't supply any.
+    fn all_pids() -> io::Result<Vec<pid_t>> {
+        let mut pids: Vec<pid_t> = fs::read_dir("/proc")?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<pid_t>().ok())
+            .collect();
+        pids.sort_unstable();
+        Ok(pids)
+    }
+
+    /// Parses a `--hex` needle, a sequence of whitespace-separated hex bytes
+    /// such as "de ad be ef".
+    fn parse_hex(needle: &str) -> Result<Vec<u8>, String> {
+        needle
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).map_err(|err| format!("{byte:?}: {err}")))
+            .collect()
+    }
+
+    /// Resolves a `--user`/`--uid` argument, which may be either a username or a numeric UID.
+    fn resolve_uid(user: &str) -> io::Result<Uid> {
+        if let Ok(uid) = user.parse::<u32>() {
+            return Ok(Uid::from_raw(uid));
+        }
+
+        User::from_name(user)?
+            .map(|user| user.uid)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no such user: {user}")))
+    }
+
     fn print_info(&self, pid: pid_t) -> io::Result<()> {
         let mut cmdline = fs::read_to_string(format!("/proc/{pid}/cmdline"))?;
         for i in 0..cmdline.len() {
@@ -73,8 +228,14 @@ impl Args {
         cmdline.pop();
 
         let metadata = fs::metadata(format!("/proc/{pid}"))?;
-        let user = User::from_uid(Uid::from_raw(metadata.uid()))?.unwrap();
-        let name = &user.name;
+        let uid = metadata.uid();
+        // Processes running under a UID with no NSS/`/etc/passwd` entry (e.g.
+        // dynamic users in containers) are common; fall back to the raw UID
+        // instead of failing the whole scan over it.
+        let name = match User::from_uid(Uid::from_raw(uid))? {
+            Some(user) => user.name,
+            None => uid.to_string(),
+        };
 
         let needle = &self.needle;
         eprintln!("Searching for {needle:?} in process {pid} by {name}: `{cmdline}`");